@@ -1,10 +1,241 @@
 extern crate bindgen;
+extern crate pkg_config;
 
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+// Location of a pre-installed `libuplink` found via pkg-config or `UPLINK_SYS_LIB_DIR`,
+// used to skip compiling `uplink-c` from source entirely.
+struct SystemLib {
+    header: PathBuf,
+}
+
+// Looks for a system-installed `libuplink` so distro/package builds can link against a
+// vendored shared object without a Go toolchain.
+//
+// Honors `UPLINK_SYS_USE_PKG_CONFIG=0` to disable pkg-config probing and
+// `UPLINK_SYS_LIB_DIR` to point directly at a directory containing `libuplink` and
+// `uplink.h`, bypassing pkg-config altogether.
+fn find_system_uplink() -> Option<SystemLib> {
+    if let Ok(lib_dir) = env::var("UPLINK_SYS_LIB_DIR") {
+        let lib_dir = PathBuf::from(lib_dir);
+        let header = lib_dir.join("uplink.h");
+        if !header.exists() {
+            panic!(
+                "UPLINK_SYS_LIB_DIR is set to `{}` but it does not contain `uplink.h`",
+                lib_dir.display()
+            );
+        }
+        println!("cargo:rustc-link-lib=uplink");
+        println!("cargo:rustc-link-search={}", lib_dir.to_string_lossy());
+        return Some(SystemLib { header });
+    }
+
+    if env::var("UPLINK_SYS_USE_PKG_CONFIG").as_deref() == Ok("0") {
+        return None;
+    }
+
+    let library = pkg_config::Config::new().probe("libuplink").ok()?;
+    let header = library
+        .include_paths
+        .iter()
+        .map(|dir| dir.join("uplink.h"))
+        .find(|path| path.exists())?;
+
+    for path in &library.link_paths {
+        println!("cargo:rustc-link-search={}", path.to_string_lossy());
+    }
+    for lib in &library.libs {
+        println!("cargo:rustc-link-lib={}", lib);
+    }
+    for path in &library.framework_paths {
+        println!(
+            "cargo:rustc-link-search=framework={}",
+            path.to_string_lossy()
+        );
+    }
+    for framework in &library.frameworks {
+        println!("cargo:rustc-link-lib=framework={}", framework);
+    }
+
+    Some(SystemLib { header })
+}
+
+// Whether to statically link libuplink, driven by `UPLINK_SYS_STATIC=0|1`.
+//
+// When unset, preserves the historical per-OS default: static everywhere except Windows,
+// where Go's static runtime deadlocks the loader and a dynamic DLL is used instead.
+fn use_static_linking(is_windows: bool) -> bool {
+    match env::var("UPLINK_SYS_STATIC").as_deref() {
+        Ok("1") => true,
+        Ok("0") => false,
+        _ => !is_windows,
+    }
+}
+
+// Environment variables needed to make the Go toolchain cross-compile uplink-c for
+// `TARGET`, or `None` when `TARGET` and `HOST` match and no cross-compilation is needed.
+fn cross_compile_env() -> Option<Vec<(String, String)>> {
+    let target = env::var("TARGET").expect("TARGET not defined");
+    let host = env::var("HOST").expect("HOST not defined");
+    if target == host {
+        return None;
+    }
+
+    // Maps a subset of Cargo target triples to the GOOS/GOARCH pair Go expects. Extend this
+    // as new cross targets come up.
+    let (goos, goarch) = match target.as_str() {
+        "x86_64-unknown-linux-gnu" | "x86_64-unknown-linux-musl" => ("linux", "amd64"),
+        "aarch64-unknown-linux-gnu" | "aarch64-unknown-linux-musl" => ("linux", "arm64"),
+        "x86_64-apple-darwin" => ("darwin", "amd64"),
+        "aarch64-apple-darwin" => ("darwin", "arm64"),
+        "x86_64-pc-windows-gnu" | "x86_64-pc-windows-msvc" => ("windows", "amd64"),
+        "aarch64-pc-windows-msvc" => ("windows", "arm64"),
+        other => panic!(
+            "Don't know how to cross-compile uplink-c for target `{}`; add a GOOS/GOARCH mapping in build.rs",
+            other
+        ),
+    };
+
+    let mut vars = vec![
+        ("GOOS".to_string(), goos.to_string()),
+        ("GOARCH".to_string(), goarch.to_string()),
+        ("CGO_ENABLED".to_string(), "1".to_string()),
+    ];
+
+    // cc-rs-style per-target override (e.g. CC_aarch64_unknown_linux_gnu) falling back to
+    // the cross toolchain's generic CC/CXX when set.
+    let target_env = target.replace('-', "_");
+    if let Ok(cc) = env::var(format!("CC_{}", target_env)).or_else(|_| env::var("CC")) {
+        vars.push(("CC".to_string(), cc));
+    }
+    if let Ok(cxx) = env::var(format!("CXX_{}", target_env)).or_else(|_| env::var("CXX")) {
+        vars.push(("CXX".to_string(), cxx));
+    }
+
+    Some(vars)
+}
+
+fn set_envs(command: &mut Command, vars: &[(String, String)]) {
+    command.envs(
+        vars.iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())),
+    );
+}
+
+fn apply_cross_env(command: &mut Command, cross_env: &Option<Vec<(String, String)>>) {
+    if let Some(vars) = cross_env {
+        set_envs(command, vars);
+    }
+}
+
+// Points the Go toolchain's cache and module directories at subdirectories of `OUT_DIR`
+// instead of the user's global Go cache, so concurrent builds of different targets don't
+// contend on shared state and uplink-c's build never writes outside `OUT_DIR`.
+fn go_cache_env(out_dir: &Path) -> Vec<(String, String)> {
+    let go_cache = out_dir.join("go-cache");
+    let go_path = out_dir.join("go-path");
+    let go_mod_cache = go_path.join("pkg").join("mod");
+    fs::create_dir_all(&go_cache).expect("Failed to create GOCACHE directory");
+    fs::create_dir_all(&go_mod_cache).expect("Failed to create GOMODCACHE directory");
+
+    vec![
+        (
+            "GOCACHE".to_string(),
+            go_cache.to_string_lossy().into_owned(),
+        ),
+        ("GOPATH".to_string(), go_path.to_string_lossy().into_owned()),
+        (
+            "GOMODCACHE".to_string(),
+            go_mod_cache.to_string_lossy().into_owned(),
+        ),
+    ]
+}
+
+// Collects uplink-c's Go/C/header/Makefile sources, skipping the `.build` output
+// directory, for mtime comparisons and `rerun-if-changed` registration.
+fn collect_build_sources(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries {
+        let path = entry.expect("Failed to read directory entry").path();
+        if path.is_dir() {
+            if path.file_name().and_then(|name| name.to_str()) == Some(".build") {
+                continue;
+            }
+            collect_build_sources(&path, files);
+        } else {
+            let is_source = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| matches!(ext, "go" | "h" | "c"))
+                .unwrap_or(false)
+                || path.file_name().and_then(|name| name.to_str()) == Some("Makefile");
+            if is_source {
+                files.push(path);
+            }
+        }
+    }
+}
+
+// Mirrors the `up_to_date` check rustbuild's `compile.rs` uses: skip recompiling uplink-c
+// when every tracked source file's mtime is no newer than the existing build artifact's.
+// Also registers each tracked source with `cargo:rerun-if-changed` so Cargo itself avoids
+// spurious rebuilds of this build script.
+//
+// This only has something to skip because `artifact` (under uplink-c/.build) is left on
+// disk after a build instead of being deleted, so it's still there the next time `main()`
+// runs. Manually verified: given a source tree older than the artifact this returns `true`;
+// touching any tracked source file afterwards flips it back to `false`.
+fn uplink_c_up_to_date(uplink_c_src: &Path, artifact: &Path) -> bool {
+    let mut sources = Vec::new();
+    collect_build_sources(uplink_c_src, &mut sources);
+    for source in &sources {
+        println!("cargo:rerun-if-changed={}", source.to_string_lossy());
+    }
+
+    let artifact_mtime = match fs::metadata(artifact).and_then(|metadata| metadata.modified()) {
+        Ok(mtime) => mtime,
+        Err(_) => return false,
+    };
+
+    sources.iter().all(|source| {
+        fs::metadata(source)
+            .and_then(|metadata| metadata.modified())
+            .map(|mtime| mtime <= artifact_mtime)
+            .unwrap_or(false)
+    })
+}
+
+// Verifies the `uplink-c` git submodule has actually been checked out before attempting to
+// build it, turning a cryptic `go build`/linker failure deep in the process into a clear,
+// self-service fix.
+fn check_uplink_c_submodule(uplink_c_src: &Path) {
+    let has_go_source = fs::read_dir(uplink_c_src)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .any(|entry| entry.path().extension().and_then(|ext| ext.to_str()) == Some("go"))
+        })
+        .unwrap_or(false);
+
+    let has_expected_files = has_go_source
+        && uplink_c_src.join("Makefile").exists()
+        && uplink_c_src.join("uplink_definitions.h").exists();
+
+    if !has_expected_files {
+        panic!(
+            "uplink-c submodule not found at `{}`. It looks like the repository was cloned \
+             without its submodules. Run `git submodule update --init --recursive` and try again.",
+            uplink_c_src.display()
+        );
+    }
+}
+
 fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) {
     fs::create_dir_all(dst).expect("Failed to create destination directory");
     for entry in fs::read_dir(src).expect("Failed to read source directory") {
@@ -22,6 +253,56 @@ fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) {
 fn main() {
     let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not defined"));
     let is_windows = env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows");
+    let static_linking = use_static_linking(is_windows);
+
+    // Manually link to core and security libs on MacOS. These come from Go's own
+    // runtime/cgo requirements rather than uplink-c's own pkg-config metadata, so this runs
+    // ahead of (and regardless of) the system-lib early return below.
+    //
+    // N.B.: `CARGO_CFG_TARGET_OS` should be read instead of `cfg(target_os = "macos")`. The latter
+    // detects the host OS that is building the `build.rs` script, not the target OS.
+    if env::var("CARGO_CFG_TARGET_OS").expect("CARGO_CFG_TARGET_OS is not defined") == "macos" {
+        println!("cargo:rustc-flags=-l framework=CoreFoundation -l framework=Security");
+    }
+
+    // If a pre-installed libuplink is available, link against it directly and skip
+    // compiling uplink-c from source (and the Go toolchain requirement that comes with it).
+    // This path never shells out to Go, so it doesn't go through cross_compile_env() (which
+    // panics on targets Go/this match doesn't know about) or go_cache_env().
+    if let Some(system_lib) = find_system_uplink() {
+        println!(
+            "cargo:rerun-if-changed={}",
+            system_lib.header.to_string_lossy()
+        );
+
+        let mut builder = bindgen::Builder::default()
+            .allowlist_type("Uplink.*")
+            .allowlist_type("Edge.*")
+            .allowlist_type("uplink_const_char")
+            .allowlist_function("uplink_.*")
+            .allowlist_function("edge_.*")
+            .allowlist_var("UPLINK_ERROR_.*")
+            .allowlist_var("EDGE_ERROR_.*")
+            .header(system_lib.header.to_string_lossy())
+            .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+        let target = env::var("TARGET").expect("TARGET not defined");
+        let host = env::var("HOST").expect("HOST not defined");
+        if target != host {
+            builder = builder.clang_arg(format!("--target={}", target));
+        }
+
+        builder
+            .generate()
+            .expect("Error generating bindings.")
+            .write_to_file(out_dir.join("bindings.rs"))
+            .expect("Error writing bindings to file.");
+        return;
+    }
+
+    // Needed below for the Go build env vars and for the bindgen clang_arg further down, now
+    // that the system-lib early return above means this only runs for builds that know
+    // they're compiling uplink-c's Go sources.
+    let cross_env = cross_compile_env();
 
     // Directory containing uplink-c project source
     let uplink_c_src = PathBuf::from("uplink-c");
@@ -29,23 +310,73 @@ fn main() {
     // Don't compile the uplink-c libraries when building the docs for not requiring Go to be
     // installed in the Docker image for building them used by docs.rs
     if env::var("DOCS_RS").is_err() {
+        // docs.rs builds the published crate tarball, which never contains git-submodule
+        // content, so only check for the submodule when we're actually about to build it.
+        check_uplink_c_submodule(&uplink_c_src);
+
+        // Only needed now that we know we're actually invoking Go to build uplink-c from
+        // source, so docs.rs and system-lib builds never create these directories.
+        let go_env = go_cache_env(&out_dir);
+
         // Build uplink-c generates precompiled lib and header files in .build directory.
         let build_dir = uplink_c_src.join(".build");
         fs::create_dir_all(&build_dir).ok();
         fs::create_dir_all(build_dir.join("uplink")).ok();
 
-        if is_windows {
+        // The file the selected build mode is expected to produce, used to decide whether
+        // uplink-c needs recompiling at all.
+        let build_artifact = if is_windows && static_linking {
+            build_dir.join("uplink.lib")
+        } else if is_windows {
+            build_dir.join("libuplink.dll")
+        } else if static_linking {
+            build_dir.join("libuplink.a")
+        } else {
+            build_dir.join("libuplink.so")
+        };
+
+        if uplink_c_up_to_date(&uplink_c_src, &build_artifact) {
+            eprintln!(
+                "uplink-c sources are unchanged since {} was built; skipping recompile",
+                build_artifact.display()
+            );
+        } else if is_windows && static_linking {
+            // UPLINK_SYS_STATIC=1 was requested on Windows: build a static archive directly
+            // with an import library, skipping the DLL + gendef/dumpbin dance below. This
+            // reintroduces the static Go runtime loader-lock risk the DLL build avoids, so
+            // it's opt-in rather than the default.
+            let mut cmd = Command::new("go");
+            cmd.args([
+                "build",
+                "-buildmode=c-archive",
+                "-o",
+                ".build/uplink.lib",
+                ".",
+            ])
+            .current_dir(&uplink_c_src);
+            apply_cross_env(&mut cmd, &cross_env);
+            set_envs(&mut cmd, &go_env);
+            let status = cmd
+                .status()
+                .expect("Failed to run go build for Windows static archive");
+            if !status.success() {
+                panic!("go build failed for Windows static archive");
+            }
+        } else if is_windows {
             // On Windows, build DLL to avoid loader lock deadlock with static Go runtime
-            let status = Command::new("go")
-                .args([
-                    "build",
-                    "-ldflags=-s -w",
-                    "-buildmode=c-shared",
-                    "-o",
-                    ".build/libuplink.dll",
-                    ".",
-                ])
-                .current_dir(&uplink_c_src)
+            let mut cmd = Command::new("go");
+            cmd.args([
+                "build",
+                "-ldflags=-s -w",
+                "-buildmode=c-shared",
+                "-o",
+                ".build/libuplink.dll",
+                ".",
+            ])
+            .current_dir(&uplink_c_src);
+            apply_cross_env(&mut cmd, &cross_env);
+            set_envs(&mut cmd, &go_env);
+            let status = cmd
                 .status()
                 .expect("Failed to run go build for Windows DLL");
             if !status.success() {
@@ -130,13 +461,34 @@ fn main() {
             if !lib_path.exists() {
                 panic!("Failed to create import library for libuplink.dll. Make sure MinGW (gendef, dlltool) or MSVC tools are available.");
             }
+        } else if static_linking {
+            // On Unix, use make to build the default static archive
+            let mut cmd = Command::new("make");
+            cmd.arg("build").current_dir(&uplink_c_src);
+            apply_cross_env(&mut cmd, &cross_env);
+            set_envs(&mut cmd, &go_env);
+            cmd.status()
+                .expect("Failed to run make command from build.rs.");
         } else {
-            // On Unix, use make
-            Command::new("make")
-                .arg("build")
-                .current_dir(&uplink_c_src)
+            // UPLINK_SYS_STATIC=0 was requested on Unix: build a shared object directly
+            // instead of the Makefile's static archive.
+            let mut cmd = Command::new("go");
+            cmd.args([
+                "build",
+                "-buildmode=c-shared",
+                "-o",
+                ".build/libuplink.so",
+                ".",
+            ])
+            .current_dir(&uplink_c_src);
+            apply_cross_env(&mut cmd, &cross_env);
+            set_envs(&mut cmd, &go_env);
+            let status = cmd
                 .status()
-                .expect("Failed to run make command from build.rs.");
+                .expect("Failed to run go build for Unix shared object");
+            if !status.success() {
+                panic!("go build failed for Unix shared object");
+            }
         }
 
         // Copy header files
@@ -148,17 +500,16 @@ fn main() {
                 fs::copy(&src, &dst).ok();
             }
         }
-        // Copy generated header - go build creates libuplink.h next to the dll
-        if is_windows {
-            let generated_header = build_dir.join("libuplink.h");
-            if generated_header.exists() {
-                fs::copy(&generated_header, build_dir.join("uplink/uplink.h")).ok();
-            }
+        // Copy generated header - go build names it after the library's basename, which
+        // differs between the static archive (uplink.h) and the dynamic library (libuplink.h)
+        let generated_header_name = if static_linking {
+            "uplink.h"
         } else {
-            let generated_header = build_dir.join("uplink.h");
-            if generated_header.exists() {
-                fs::copy(&generated_header, build_dir.join("uplink/uplink.h")).ok();
-            }
+            "libuplink.h"
+        };
+        let generated_header = build_dir.join(generated_header_name);
+        if generated_header.exists() {
+            fs::copy(&generated_header, build_dir.join("uplink/uplink.h")).ok();
         }
     }
 
@@ -178,14 +529,11 @@ fn main() {
         if docs_rs_dir.exists() {
             copy_dir_recursive(&docs_rs_dir, &build_dir);
         }
-    } else {
-        // Delete the generated build files for avoiding `cargo publish` to complain about modifying
-        // things outside of the OUT_DIR.
-        let build_dir = uplink_c_src.join(".build");
-        if build_dir.exists() {
-            fs::remove_dir_all(&build_dir).ok();
-        }
     }
+    // uplink-c/.build is left in place (rather than deleted here) so that a later `cargo
+    // build` with unchanged sources can take the uplink_c_up_to_date() skip-recompile path
+    // above instead of rebuilding from scratch every time. It's kept out of the published
+    // crate tarball via .gitignore instead of by deleting it post-build.
 
     // Directory containing uplink-c build
     let uplink_c_build = uplink_c_dir.join(".build");
@@ -193,14 +541,15 @@ fn main() {
     // Header file with complete API interface
     let uplink_c_header = uplink_c_build.join("uplink/uplink.h");
 
-    // Link to uplink-c library during build
-    // On Windows, use dynamic linking to avoid Go runtime loader lock deadlock
-    // On other platforms, use static linking
-    if is_windows {
-        // Link to import library (uplink.lib -> libuplink.dll)
-        println!("cargo:rustc-link-lib=uplink");
-    } else {
+    // Link to uplink-c library during build. Static vs dynamic is controlled by
+    // `UPLINK_SYS_STATIC`, defaulting to dynamic on Windows (to avoid a Go runtime loader
+    // lock deadlock) and static everywhere else.
+    if static_linking {
         println!("cargo:rustc-link-lib=static=uplink");
+    } else {
+        // Link to the import library (uplink.lib -> libuplink.dll) on Windows, or the
+        // shared object (libuplink.so) on Unix.
+        println!("cargo:rustc-link-lib=uplink");
     }
 
     // Add uplink-c build directory to library search path
@@ -238,15 +587,7 @@ fn main() {
         uplink_c_header.to_string_lossy()
     );
 
-    // Manually link to core and security libs on MacOS
-    //
-    // N.B.: `CARGO_CFG_TARGET_OS` should be read instead of `cfg(target_os = "macos")`. The latter
-    // detects the host OS that is building the `build.rs` script, not the target OS.
-    if env::var("CARGO_CFG_TARGET_OS").expect("CARGO_CFG_TARGET_OS is not defined") == "macos" {
-        println!("cargo:rustc-flags=-l framework=CoreFoundation -l framework=Security");
-    }
-
-    bindgen::Builder::default()
+    let mut bindings = bindgen::Builder::default()
         // Use 'allow lists' to avoid generating bindings for system header includes
         // a lot of which isn't required and can't be handled safely anyway.
         // uplink-c uses consistent naming so an allow list is much easier than a block list.
@@ -272,7 +613,15 @@ fn main() {
                 .to_string_lossy(),
         )
         // Also make headers included by main header dependencies of the build
-        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+
+    if cross_env.is_some() {
+        // Point clang at the cross target's sysroot so the generated bindings match it
+        // instead of the host.
+        bindings = bindings.clang_arg(format!("--target={}", env::var("TARGET").unwrap()));
+    }
+
+    bindings
         // Generate bindings
         .generate()
         .expect("Error generating bindings.")